@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use crate::instructions::Instruction;
+
+// Two-pass text assembler. Pass one walks the parsed items and accumulates the
+// byte length of every instruction so we can build a label -> address map;
+// pass two emits the actual bytes, substituting each `@label` reference with its
+// resolved 16-bit big-endian address (matching how `fetch16` recombines bytes as
+// `(byte1 << 8) | byte2`).
+
+#[derive(Debug)]
+pub enum AssemblerError {
+    UnknownMnemonic(String),
+    RegisterOutOfBounds(String),
+    UndefinedLabel(String),
+    UnexpectedOperand(String),
+    WrongOperandCount(String),
+}
+
+// A single operand as it comes out of the lexer. Labels stay unresolved until
+// pass two, because a reference can point at a label defined later in the source.
+#[derive(Debug)]
+enum Operand {
+    Register(u8),
+    Literal(u16),
+    Label(String),
+}
+
+// An item is either a `label:` definition (which occupies no bytes) or a single
+// instruction together with its operands.
+#[derive(Debug)]
+enum Item {
+    Label(String),
+    Instruction { mnemonic: String, operands: Vec<Operand> },
+}
+
+// Map a register token (`r1`..`r8`, `acc`, `ip`, `sp`, `fp`) onto the index used
+// by `fetch_register_index` / the `register_names` table on the `Cpu`.
+fn parse_register(token: &str) -> Option<u8> {
+    match token {
+        "ip" => Some(0),
+        "acc" => Some(1),
+        "sp" => Some(10),
+        "fp" => Some(11),
+        _ => {
+            let number = token.strip_prefix('r')?.parse::<u8>().ok()?;
+            if (1..=8).contains(&number) {
+                Some(number + 1) // r1 lives at index 2, r8 at index 9
+            } else {
+                None
+            }
+        }
+    }
+}
+
+// A token shaped like a register (`r<digits>`) that `parse_register` rejected is
+// an out-of-range register such as `r0` or `r9`, not a mnemonic.
+fn looks_like_register(token: &str) -> bool {
+    token
+        .strip_prefix('r')
+        .map(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or(false)
+}
+
+// Parse a hex (`0x..`) or decimal literal into a 16-bit value.
+fn parse_literal(token: &str) -> Option<u16> {
+    if let Some(hex) = token.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16).ok()
+    } else {
+        token.parse::<u16>().ok()
+    }
+}
+
+// Lex the source into a flat list of items. Commas are treated as whitespace so
+// `mov 0x1234, r1` and `mov 0x1234 r1` lex the same way.
+fn lex(source: &str) -> Result<Vec<Item>, AssemblerError> {
+    let mut items: Vec<Item> = Vec::new();
+
+    for raw_line in source.lines() {
+        // Strip `;` comments, then split on whitespace/commas.
+        let line = raw_line.split(';').next().unwrap_or("");
+        for word in line.replace(',', " ").split_whitespace() {
+            if let Some(label) = word.strip_suffix(':') {
+                items.push(Item::Label(label.to_string()));
+            } else if let Some(label) = word.strip_prefix('@') {
+                push_operand(&mut items, Operand::Label(label.to_string()))?;
+            } else if let Some(register) = parse_register(word) {
+                push_operand(&mut items, Operand::Register(register))?;
+            } else if looks_like_register(word) {
+                return Err(AssemblerError::RegisterOutOfBounds(word.to_string()));
+            } else if let Some(literal) = parse_literal(word) {
+                push_operand(&mut items, Operand::Literal(literal))?;
+            } else {
+                // Anything left is taken to be a mnemonic; validity is checked
+                // when we size/encode the instruction.
+                items.push(Item::Instruction {
+                    mnemonic: word.to_lowercase(),
+                    operands: Vec::new(),
+                });
+            }
+        }
+    }
+
+    Ok(items)
+}
+
+// Attach an operand to the instruction currently being built. An operand with no
+// preceding mnemonic is a syntax error.
+fn push_operand(items: &mut Vec<Item>, operand: Operand) -> Result<(), AssemblerError> {
+    match items.last_mut() {
+        Some(Item::Instruction { operands, .. }) => {
+            operands.push(operand);
+            Ok(())
+        }
+        _ => Err(AssemblerError::UnexpectedOperand(format!("{:?}", operand))),
+    }
+}
+
+// Resolve a mnemonic + its operand shapes down to a concrete `Instruction`.
+fn resolve(mnemonic: &str, operands: &[Operand]) -> Result<Instruction, AssemblerError> {
+    use Operand::*;
+    let wrong_count = || AssemblerError::WrongOperandCount(mnemonic.to_string());
+    match mnemonic {
+        "mov" => match operands {
+            [Literal(_), Register(_)] => Ok(Instruction::MoveLiteralToRegister),
+            [Register(_), Register(_)] => Ok(Instruction::MoveRegisterToRegister),
+            _ => Err(wrong_count()),
+        },
+        "add" => match operands {
+            [Register(_), Register(_)] => Ok(Instruction::AddRegisterToRegister),
+            _ => Err(wrong_count()),
+        },
+        "jne" => match operands {
+            [Literal(_), Literal(_) | Label(_)] => Ok(Instruction::JumpNotEq),
+            _ => Err(wrong_count()),
+        },
+        "push" => match operands {
+            [Literal(_)] => Ok(Instruction::PushLiteral),
+            [Register(_)] => Ok(Instruction::PushRegister),
+            _ => Err(wrong_count()),
+        },
+        "pop" => match operands {
+            [Register(_)] => Ok(Instruction::Pop),
+            _ => Err(wrong_count()),
+        },
+        "call" => match operands {
+            [Literal(_) | Label(_)] => Ok(Instruction::CallLiteral),
+            [Register(_)] => Ok(Instruction::CallRegister),
+            _ => Err(wrong_count()),
+        },
+        "ret" => match operands {
+            [] => Ok(Instruction::Return),
+            _ => Err(wrong_count()),
+        },
+        _ => Err(AssemblerError::UnknownMnemonic(mnemonic.to_string())),
+    }
+}
+
+// Encoded byte length of an instruction: one opcode byte plus one byte per
+// register operand and two bytes per literal/label operand.
+fn encoded_len(operands: &[Operand]) -> u16 {
+    let operand_bytes: u16 = operands
+        .iter()
+        .map(|operand| match operand {
+            Operand::Register(_) => 1,
+            Operand::Literal(_) | Operand::Label(_) => 2,
+        })
+        .sum();
+    1 + operand_bytes
+}
+
+/// Assemble `source` into a byte buffer ready to be copied into the `Memory`
+/// returned by `create_memory`.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssemblerError> {
+    let items = lex(source)?;
+
+    // Pass one: resolve label addresses by accumulating instruction sizes.
+    let mut labels: HashMap<String, u16> = HashMap::new();
+    let mut address: u16 = 0;
+    for item in &items {
+        match item {
+            Item::Label(name) => {
+                labels.insert(name.clone(), address);
+            }
+            Item::Instruction { mnemonic, operands } => {
+                resolve(mnemonic, operands)?;
+                address += encoded_len(operands);
+            }
+        }
+    }
+
+    // Pass two: emit bytes, substituting resolved label addresses.
+    let mut bytes: Vec<u8> = Vec::new();
+    for item in &items {
+        let Item::Instruction { mnemonic, operands } = item else {
+            continue;
+        };
+        let instruction = resolve(mnemonic, operands)?;
+        bytes.push(instruction.into());
+        for operand in operands {
+            match operand {
+                Operand::Register(index) => bytes.push(*index),
+                Operand::Literal(value) => emit16(&mut bytes, *value),
+                Operand::Label(name) => {
+                    let value = *labels
+                        .get(name)
+                        .ok_or_else(|| AssemblerError::UndefinedLabel(name.clone()))?;
+                    emit16(&mut bytes, value);
+                }
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+// Emit a 16-bit value big-endian, mirroring `(byte1 << 8) | byte2` in `fetch16`.
+fn emit16(bytes: &mut Vec<u8>, value: u16) {
+    bytes.push(((value >> 8) & 0xFF) as u8);
+    bytes.push((value & 0xFF) as u8);
+}
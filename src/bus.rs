@@ -0,0 +1,112 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{create_memory::Memory, cpu::CpuError};
+
+// A peripheral wired onto the bus. Addresses are presented to the device as an
+// offset from the start of its mapped region, so a device doesn't need to know
+// where it lives in the global address space.
+pub trait Device {
+    fn read16(&self, offset: u16) -> Result<u16, CpuError>;
+    fn write16(&mut self, offset: u16, value: u16) -> Result<(), CpuError>;
+}
+
+// A device mapped into an inclusive `[start, end]` slice of the address space.
+struct MappedRegion {
+    start: u16,
+    end: u16,
+    device: Box<dyn Device>,
+}
+
+// Routes memory accesses either to a mapped device or, as a fallback, to plain
+// RAM that covers the whole address space. The RAM is shared via the same
+// `Rc<RefCell<Memory>>` returned by `create_memory`, so bytes written directly
+// into that buffer (e.g. a loaded program) are still visible here.
+pub struct Bus {
+    ram: Rc<RefCell<Memory>>,
+    regions: Vec<MappedRegion>,
+}
+
+impl Bus {
+    pub fn new(ram: Rc<RefCell<Memory>>) -> Self {
+        Bus {
+            ram,
+            regions: Vec::new(),
+        }
+    }
+
+    // Map a device into `[start, end]`. Later mappings take precedence over the
+    // RAM fallback for the addresses they cover.
+    pub fn map(&mut self, start: u16, end: u16, device: Box<dyn Device>) {
+        self.regions.push(MappedRegion { start, end, device });
+    }
+
+    fn region_for(&self, address: u16) -> Option<usize> {
+        self.regions
+            .iter()
+            .position(|region| address >= region.start && address <= region.end)
+    }
+
+    // Byte access always targets RAM; instruction opcodes live in RAM.
+    pub fn read8(&self, address: u16) -> Result<u8, CpuError> {
+        self.ram
+            .borrow()
+            .get(address as usize)
+            .copied()
+            .ok_or(CpuError::MemoryOutOfBounds { addr: address })
+    }
+
+    pub fn read16(&self, address: u16) -> Result<u16, CpuError> {
+        if let Some(index) = self.region_for(address) {
+            let region = &self.regions[index];
+            return region.device.read16(address - region.start);
+        }
+        // A 16-bit access to the final byte would straddle the end of the
+        // 64 KiB space (the second byte wraps to 0x0000).
+        if address == u16::MAX {
+            return Err(CpuError::MemoryAlignment { addr: address });
+        }
+        let ram = self.ram.borrow();
+        let byte1 = *ram
+            .get(address as usize)
+            .ok_or(CpuError::MemoryOutOfBounds { addr: address })?;
+        let byte2 = *ram
+            .get(address as usize + 1)
+            .ok_or(CpuError::MemoryOutOfBounds { addr: address + 1 })?;
+        Ok(((byte1 as u16) << 8) | (byte2 as u16)) // Big-Endian Version
+    }
+
+    pub fn write16(&mut self, address: u16, value: u16) -> Result<(), CpuError> {
+        if let Some(index) = self.region_for(address) {
+            let region = &mut self.regions[index];
+            return region.device.write16(address - region.start, value);
+        }
+        if address == u16::MAX {
+            return Err(CpuError::MemoryAlignment { addr: address });
+        }
+        let high_byte = ((value >> 8) & 0xFF) as u8;
+        let low_byte = (value & 0xFF) as u8;
+        let mut ram = self.ram.borrow_mut();
+        let len = ram.len();
+        if address as usize + 1 >= len {
+            return Err(CpuError::MemoryOutOfBounds { addr: address });
+        }
+        ram[address as usize] = high_byte; // Big Edian way
+        ram[address as usize + 1] = low_byte;
+        Ok(())
+    }
+}
+
+// A memory-mapped console sink: writing a 16-bit word prints it. Map it at, say,
+// 0xFF00 and a store into that address becomes console output.
+pub struct ConsoleOutput;
+
+impl Device for ConsoleOutput {
+    fn read16(&self, _offset: u16) -> Result<u16, CpuError> {
+        Ok(0)
+    }
+
+    fn write16(&mut self, _offset: u16, value: u16) -> Result<(), CpuError> {
+        println!("{}", value);
+        Ok(())
+    }
+}
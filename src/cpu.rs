@@ -1,7 +1,9 @@
 use core::num;
+use std::io::{self, Read, Write};
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
+    bus::{Bus, Device},
     create_memory::{create_registers, Memory},
     instructions::Instruction,
 };
@@ -26,16 +28,87 @@ pub enum RegisterName {
 pub enum CpuError {
     RegisterNameDoesNotExist,
     RegisterOutOfBounds,
-    MemoryOutOfBounds,
+    MemoryOutOfBounds { addr: u16 },
+    MemoryAlignment { addr: u16 },
     InvalidInstruction,
+    DivideByZero,
+    UnhandledTrap(u16),
+    BreakpointHit(u16),
 }
 
+// Hook that lets an embedder service `Ecall` traps, e.g. to implement syscalls
+// that talk to the outside world. The handler gets `&mut Cpu` so it can read
+// argument registers and write results back.
+pub trait HostCall {
+    fn call(&mut self, cpu: &mut Cpu, num: u16) -> Result<(), CpuError>;
+}
+
+// Default host-call handler wiring up a couple of console I/O syscalls so a
+// program can produce output: call 0 prints the u16 in R1, call 1 reads one
+// byte from stdin into R2.
+pub struct ConsoleHostCall;
+
+impl HostCall for ConsoleHostCall {
+    fn call(&mut self, cpu: &mut Cpu, num: u16) -> Result<(), CpuError> {
+        match num {
+            0 => {
+                let value = *cpu.get_register(&RegisterName::R1)?;
+                println!("{}", value);
+                Ok(())
+            }
+            1 => {
+                let mut byte = [0u8; 1];
+                io::stdout().flush().ok();
+                let read = io::stdin().read(&mut byte).unwrap_or(0);
+                let value = if read == 0 { 0 } else { byte[0] as u16 };
+                cpu.set_register(&RegisterName::R2, value)?;
+                Ok(())
+            }
+            _ => Err(CpuError::UnhandledTrap(num)),
+        }
+    }
+}
+
+// Operand source for the math-instruction family. Encoded as a single
+// OPERAND_MODE byte: register indices (read via fetch_register_index) or
+// inline 16-bit literals (read via fetch16).
+enum OperandMode {
+    Register,
+    Literal,
+}
+
+// How the math-instruction family interprets its operands: either as raw u16
+// values or as two's-complement i16 values re-stored as their u16 bit pattern.
+enum MathType {
+    Unsigned,
+    Signed,
+}
+
+// Which arithmetic a Math* opcode performs.
+enum MathOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+// Bits of the status-flags register, set by the math and `Cmp` instructions and
+// tested by the conditional-jump instructions.
+const FLAG_ZERO: u16 = 1 << 0;
+const FLAG_CARRY: u16 = 1 << 1;
+const FLAG_NEGATIVE: u16 = 1 << 2;
+const FLAG_OVERFLOW: u16 = 1 << 3;
+
 pub struct Cpu {
     memory: Rc<RefCell<Memory>>,
+    bus: Bus,
     registers: Vec<u16>,
     register_names: Vec<RegisterName>,
     register_map: HashMap<RegisterName, usize>, // Set to u16 but it seems some of the addresses I push are bigger than that. I "attempt to add with overflow" on pop_state for the last set_register
     stack_frame_size: u16,
+    host_call: Option<Box<dyn HostCall>>,
+    flags: u16,
 }
 
 impl Cpu {
@@ -65,15 +138,37 @@ impl Cpu {
         // SEt Stack Pointer position to last in memory
         registers[register_names.len() - 2] = (memory.borrow().len() - 1 - 1) as u16;
 
+        let bus = Bus::new(memory.clone());
+
         Cpu {
             memory,
+            bus,
             registers,
             register_names,
             register_map,
             stack_frame_size: 0,
+            host_call: None,
+            flags: 0,
         }
     }
 
+    // Map a peripheral into `[start, end]` so stores/loads in that range go to
+    // the device instead of RAM.
+    pub fn map_device(&mut self, start: u16, end: u16, device: Box<dyn Device>) {
+        self.bus.map(start, end, device);
+    }
+
+    // Register a host-call handler used to service `Ecall` traps.
+    pub fn set_host_call(&mut self, host_call: Box<dyn HostCall>) {
+        self.host_call = Some(host_call);
+    }
+
+    // Write a register from outside the CPU, e.g. a host-call handler storing
+    // the result of a syscall.
+    pub fn write_register(&mut self, name: &RegisterName, value: u16) -> Result<(), CpuError> {
+        self.set_register(name, value)
+    }
+
     pub fn debug(&self) {
         self.register_names.iter().for_each(|reg_name| {
             let reg_value = self.get_register(reg_name).unwrap();
@@ -95,6 +190,21 @@ impl Cpu {
         println!("0x{:04x}: {}", address, next_n_bytes.join(" "));
     }
 
+    // Read-only snapshot of every register, paired with its name. Reuses
+    // get_register so a front-end can render state without poking internals.
+    pub fn register_snapshot(&self) -> Vec<(RegisterName, u16)> {
+        self.register_names
+            .iter()
+            .map(|name| (name.clone(), *self.get_register(name).unwrap()))
+            .collect()
+    }
+
+    // Read a single byte without advancing Ip, for a debugger peeking at the
+    // next opcode.
+    pub fn peek_byte(&self, address: u16) -> Result<u8, CpuError> {
+        self.bus.read8(address)
+    }
+
     pub fn get_register(&self, name: &RegisterName) -> Result<&u16, CpuError> {
         let register_index = self
             .register_map
@@ -116,60 +226,22 @@ impl Cpu {
 
     fn fetch(&mut self) -> Result<u8, CpuError> {
         let next_instruction_address = *self.get_register(&RegisterName::Ip)?;
-        self.set_register(&RegisterName::Ip, next_instruction_address + 1)?;
-        let instruction = self
-            .memory
-            .borrow()
-            .get(next_instruction_address as usize)
-            .copied()
-            .ok_or(CpuError::MemoryOutOfBounds);
-        return instruction;
+        self.set_register(&RegisterName::Ip, next_instruction_address.wrapping_add(1))?;
+        self.bus.read8(next_instruction_address)
     }
 
     fn fetch16(&mut self) -> Result<u16, CpuError> {
         let next_instruction_address = *self.get_register(&RegisterName::Ip)?;
-        self.set_register(&RegisterName::Ip, next_instruction_address + 2)?;
-
-        let byte1 = *self
-            .memory
-            .borrow()
-            .get(next_instruction_address as usize)
-            .ok_or(CpuError::MemoryOutOfBounds)?;
-        let byte2 = *self
-            .memory
-            .borrow()
-            .get(next_instruction_address as usize + 1)
-            .ok_or(CpuError::MemoryOutOfBounds)?;
-        // let instruction = ( byte2 as u16) << 8 | (byte1 as u16); // Little-Endian Version
-        let instruction = ((byte1 as u16) << 8) | (byte2 as u16); // Big-Endian Version
-
-        Ok(instruction)
+        self.set_register(&RegisterName::Ip, next_instruction_address.wrapping_add(2))?;
+        self.bus.read16(next_instruction_address)
     }
 
     pub fn get_memory_16(&self, instruction_address: u16) -> Result<u16, CpuError> {
-        let byte1 = *self
-            .memory
-            .borrow()
-            .get(instruction_address as usize)
-            .ok_or(CpuError::MemoryOutOfBounds)?;
-        let byte2 = *self
-            .memory
-            .borrow()
-            .get(instruction_address as usize + 1)
-            .ok_or(CpuError::MemoryOutOfBounds)?;
-        let instruction = ((byte1 as u16) << 8) | (byte2 as u16); // Big-Endian Version
-
-        Ok(instruction)
+        self.bus.read16(instruction_address)
     }
 
     fn set_memory16(&mut self, address: u16, value: u16) -> Result<(), CpuError> {
-        let first_byte = (value & 0xFF) as u8;
-        let second_byte = ((value >> 8) & 0xFF) as u8;
-
-        self.memory.borrow_mut()[address as usize] = first_byte; // Big Edian way
-        self.memory.borrow_mut()[(address + 1) as usize] = second_byte;
-
-        Ok(())
+        self.bus.write16(address, value)
     }
 
     fn fetch_register_index(&mut self) -> Result<u8, CpuError> {
@@ -188,16 +260,16 @@ impl Cpu {
     fn push(&mut self, value: u16) -> Result<(), CpuError> {
         let sp_address = *self.get_register(&RegisterName::Sp)?;
         self.set_memory16(sp_address, value)?;
-        self.set_register(&RegisterName::Sp, sp_address - 2)?;
-        self.stack_frame_size += 2;
+        self.set_register(&RegisterName::Sp, sp_address.wrapping_sub(2))?;
+        self.stack_frame_size = self.stack_frame_size.wrapping_add(2);
 
         Ok(())
     }
 
     fn pop(&mut self) -> Result<u16, CpuError> {
-        let next_sp_address = self.get_register(&RegisterName::Sp)? + 2;
+        let next_sp_address = self.get_register(&RegisterName::Sp)?.wrapping_add(2);
         self.set_register(&RegisterName::Sp, next_sp_address)?;
-        self.stack_frame_size -= 2;
+        self.stack_frame_size = self.stack_frame_size.wrapping_sub(2);
         let value = self.get_memory_16(next_sp_address)?;
         Ok(value)
     }
@@ -259,6 +331,158 @@ impl Cpu {
         Ok(())
     }
 
+    // Read a single math operand given the decoded OPERAND_MODE.
+    fn fetch_math_operand(&mut self, mode: &OperandMode) -> Result<u16, CpuError> {
+        match mode {
+            OperandMode::Register => {
+                let index = self.fetch_register_index()?;
+                self.registers
+                    .get(index as usize)
+                    .copied()
+                    .ok_or(CpuError::RegisterOutOfBounds)
+            }
+            OperandMode::Literal => self.fetch16(),
+        }
+    }
+
+    // Update the status flags from an arithmetic result. Zero and Negative come
+    // from the result itself; Carry (unsigned overflow) and Overflow (signed
+    // overflow) are supplied by the caller since they depend on the operation.
+    fn set_flags(&mut self, result: u16, carry: bool, overflow: bool) {
+        let mut flags = 0u16;
+        if result == 0 {
+            flags |= FLAG_ZERO;
+        }
+        if result & 0x8000 != 0 {
+            flags |= FLAG_NEGATIVE;
+        }
+        if carry {
+            flags |= FLAG_CARRY;
+        }
+        if overflow {
+            flags |= FLAG_OVERFLOW;
+        }
+        self.flags = flags;
+    }
+
+    fn flag_set(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+
+    // Decode and run a Math* instruction: the opcode is followed by
+    // [OPERAND_MODE][TYPE][LHS][RHS][DEST_REG]. Add/Sub/Mul wrap on overflow;
+    // Div/Mod fault on a zero divisor and otherwise split their result across
+    // the destination register and Acc.
+    fn execute_math(&mut self, op: MathOp) -> Result<(), CpuError> {
+        let mode = match self.fetch()? {
+            0 => OperandMode::Register,
+            _ => OperandMode::Literal,
+        };
+        let math_type = match self.fetch()? {
+            0 => MathType::Unsigned,
+            _ => MathType::Signed,
+        };
+        let lhs = self.fetch_math_operand(&mode)?;
+        let rhs = self.fetch_math_operand(&mode)?;
+        let dest_index = self.fetch_register_index()?;
+
+        // For Div/Mod we compute quotient/remainder and route them to the
+        // destination register and Acc; everything else yields a single result.
+        let (result, acc) = match op {
+            MathOp::Add | MathOp::Sub | MathOp::Mul => {
+                let value = match math_type {
+                    MathType::Unsigned => match op {
+                        MathOp::Add => lhs.wrapping_add(rhs),
+                        MathOp::Sub => lhs.wrapping_sub(rhs),
+                        _ => lhs.wrapping_mul(rhs),
+                    },
+                    MathType::Signed => {
+                        let l = lhs as i16;
+                        let r = rhs as i16;
+                        let signed = match op {
+                            MathOp::Add => l.wrapping_add(r),
+                            MathOp::Sub => l.wrapping_sub(r),
+                            _ => l.wrapping_mul(r),
+                        };
+                        signed as u16
+                    }
+                };
+                (value, None)
+            }
+            MathOp::Div | MathOp::Mod => {
+                if rhs == 0 {
+                    return Err(CpuError::DivideByZero);
+                }
+                let (quotient, remainder) = match math_type {
+                    MathType::Unsigned => (lhs / rhs, lhs % rhs),
+                    MathType::Signed => {
+                        let l = lhs as i16;
+                        let r = rhs as i16;
+                        ((l.wrapping_div(r)) as u16, (l.wrapping_rem(r)) as u16)
+                    }
+                };
+                // Div keeps the quotient in the destination, Mod the remainder;
+                // the unused half is stashed in Acc.
+                match op {
+                    MathOp::Div => (quotient, Some(remainder)),
+                    _ => (remainder, Some(quotient)),
+                }
+            }
+        };
+
+        // Carry/Overflow are only meaningful for add/sub; Zero and Negative
+        // follow from the stored result for every op.
+        let (carry, overflow) = match op {
+            MathOp::Add => (
+                lhs.overflowing_add(rhs).1,
+                (lhs as i16).overflowing_add(rhs as i16).1,
+            ),
+            MathOp::Sub => (
+                lhs.overflowing_sub(rhs).1,
+                (lhs as i16).overflowing_sub(rhs as i16).1,
+            ),
+            _ => (false, false),
+        };
+        self.set_flags(result, carry, overflow);
+
+        self.registers[dest_index as usize] = result;
+        if let Some(acc_value) = acc {
+            self.set_register(&RegisterName::Acc, acc_value)?;
+        }
+        Ok(())
+    }
+
+    // Decode and run a `Cmp`: [OPERAND_MODE][LHS_REG][RHS]. Computes LHS - RHS to
+    // set the status flags like a subtraction would, but stores no result.
+    fn execute_cmp(&mut self) -> Result<(), CpuError> {
+        let mode = match self.fetch()? {
+            0 => OperandMode::Register,
+            _ => OperandMode::Literal,
+        };
+        let lhs = {
+            let index = self.fetch_register_index()?;
+            self.registers
+                .get(index as usize)
+                .copied()
+                .ok_or(CpuError::RegisterOutOfBounds)?
+        };
+        let rhs = self.fetch_math_operand(&mode)?;
+        let result = lhs.wrapping_sub(rhs);
+        let carry = lhs.overflowing_sub(rhs).1;
+        let overflow = (lhs as i16).overflowing_sub(rhs as i16).1;
+        self.set_flags(result, carry, overflow);
+        Ok(())
+    }
+
+    // Apply a conditional jump: if `taken`, redirect Ip to the fetched target.
+    fn conditional_jump(&mut self, taken: bool) -> Result<(), CpuError> {
+        let address = self.fetch16()?;
+        if taken {
+            self.set_register(&RegisterName::Ip, address)?;
+        }
+        Ok(())
+    }
+
     fn execute(&mut self, instruction: Instruction) -> Result<(), CpuError> {
         match instruction {
             // Move literal value into r1 register. The literal will be the next 2 bytes in memory (16bit)
@@ -303,19 +527,13 @@ impl Cpu {
                     .ok_or_else(|| CpuError::RegisterOutOfBounds)?;
                 let register_value = *self.get_register(&register)?;
 
-                let first_byte = (register_value & 0xFF) as u8;
-                let second_byte = ((register_value >> 8) & 0xFF) as u8;
-
-                let address = self.fetch16()? as usize;
-                self.memory.borrow_mut()[address] = first_byte; // Big Edian way
-                self.memory.borrow_mut()[address + 1] = second_byte;
-                // self.memory.borrow_mut()[address] = second_byte; // Little Edian way
-                // self.memory.borrow_mut()[address + 1] = first_byte;
+                let address = self.fetch16()?;
+                self.bus.write16(address, register_value)?; // Big Edian way, via the bus
 
                 Ok(())
             }
             Instruction::MoveMemoryToRegister => {
-                let address = self.fetch16()? as usize;
+                let address = self.fetch16()?;
 
                 let register_index = self.fetch_register_index()?;
                 let register = self
@@ -324,11 +542,7 @@ impl Cpu {
                     .cloned()
                     .ok_or_else(|| CpuError::RegisterOutOfBounds)?;
 
-                let address_byte1 = self.memory.borrow()[address];
-                let address_byte2 = self.memory.borrow()[address + 1];
-
-                // let combined_bytes = ((address_byte1 as u16) << 8) | address_byte2 as u16; // Big
-                let combined_bytes = ((address_byte2 as u16) << 8) | address_byte1 as u16; // Little
+                let combined_bytes = self.bus.read16(address)?; // Big-Endian, via the bus
                 self.set_register(&register, combined_bytes)?;
 
                 Ok(())
@@ -395,6 +609,49 @@ impl Cpu {
                 self.pop_state()?;
                 Ok(())
             }
+            Instruction::MathAdd => self.execute_math(MathOp::Add),
+            Instruction::MathSub => self.execute_math(MathOp::Sub),
+            Instruction::MathMul => self.execute_math(MathOp::Mul),
+            Instruction::MathDiv => self.execute_math(MathOp::Div),
+            Instruction::MathMod => self.execute_math(MathOp::Mod),
+            Instruction::Ecall => {
+                // Take the handler out so it can borrow `&mut self` while it
+                // runs, then put it back afterwards.
+                let call_number = *self.get_register(&RegisterName::Acc)?;
+                let mut handler = self
+                    .host_call
+                    .take()
+                    .ok_or(CpuError::UnhandledTrap(call_number))?;
+                let result = handler.call(self, call_number);
+                self.host_call = Some(handler);
+                result
+            }
+            Instruction::Cmp => self.execute_cmp(),
+            Instruction::JumpEq => {
+                let taken = self.flag_set(FLAG_ZERO);
+                self.conditional_jump(taken)
+            }
+            Instruction::JumpNe => {
+                let taken = !self.flag_set(FLAG_ZERO);
+                self.conditional_jump(taken)
+            }
+            Instruction::JumpLt => {
+                let taken = self.flag_set(FLAG_NEGATIVE) != self.flag_set(FLAG_OVERFLOW);
+                self.conditional_jump(taken)
+            }
+            Instruction::JumpGt => {
+                let taken = !self.flag_set(FLAG_ZERO)
+                    && (self.flag_set(FLAG_NEGATIVE) == self.flag_set(FLAG_OVERFLOW));
+                self.conditional_jump(taken)
+            }
+            Instruction::JumpLtU => {
+                let taken = self.flag_set(FLAG_CARRY);
+                self.conditional_jump(taken)
+            }
+            Instruction::JumpGtU => {
+                let taken = !self.flag_set(FLAG_CARRY) && !self.flag_set(FLAG_ZERO);
+                self.conditional_jump(taken)
+            }
         }
     }
 
@@ -404,3 +661,33 @@ impl Cpu {
         self.execute(instruction)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::create_memory::create_memory;
+
+    // Pushing then popping at 0xFFFE, the last 16-bit slot of a 64 KiB memory,
+    // round-trips the value without a bounds or alignment fault.
+    #[test]
+    fn push_pop_at_top_of_memory_round_trips() {
+        let memory = create_memory(0x10000);
+        let mut cpu = Cpu::new(memory);
+        cpu.set_register(&RegisterName::Sp, 0xFFFE).unwrap();
+        cpu.push(0xBEEF).unwrap();
+        assert_eq!(cpu.pop().unwrap(), 0xBEEF);
+    }
+
+    // A 16-bit fetch whose operand would cross the end of the address space is
+    // an alignment fault, not a panic.
+    #[test]
+    fn fetch16_crossing_boundary_is_alignment_error() {
+        let memory = create_memory(0x10000);
+        let mut cpu = Cpu::new(memory);
+        cpu.set_register(&RegisterName::Ip, 0xFFFF).unwrap();
+        match cpu.fetch16() {
+            Err(CpuError::MemoryAlignment { addr }) => assert_eq!(addr, 0xFFFF),
+            other => panic!("expected alignment error, got {:?}", other),
+        }
+    }
+}
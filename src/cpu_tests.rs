@@ -1,6 +1,4 @@
-use std::io;
-
-use crate::{create_memory::create_memory, instructions::Instruction, cpu::{Cpu, CpuError, RegisterName}};
+use crate::{create_memory::create_memory, instructions::Instruction, cpu::{Cpu, CpuError}, debugger::Debugger};
 
 macro_rules! write_instruction {
     ($writable_bytes:ident, $i:ident, $($data:expr),* $(,)? ) => {
@@ -11,20 +9,22 @@ macro_rules! write_instruction {
     };
 }
 
-fn step_instruction_forever(cpu: &mut Cpu) ->Result<(), CpuError> {
-    let stdin: io::Stdin = io::stdin();
-    let mut stdout = io::stdout();
-
-    loop {
-        cpu.debug();
-        cpu.view_memory_at(*cpu.get_register(&RegisterName::Ip)? as usize, 8);
-        cpu.view_memory_at(0xffff - 1 - 42, 44); // minus 6 more bytes so we can see the 8 bytes at beginning of stack
-
-        println!("Press any key to continue...");
-        io::stdin().read_line(&mut "".to_string())
-                   .ok()
-                   .expect("Failed to read line");
-        cpu.step()?;
+// Drive the program with the debugger: run until a breakpoint is hit, then
+// print register and memory state at the point where execution actually stops.
+fn run_with_debugger(cpu: Cpu, breakpoint: u16) -> Result<(), CpuError> {
+    let mut debugger = Debugger::new(cpu);
+    debugger.add_breakpoint(breakpoint);
+
+    match debugger.run_until_break() {
+        Err(CpuError::BreakpointHit(address)) => {
+            println!("Hit breakpoint at 0x{:04x}", address);
+            for (name, value) in debugger.register_snapshot() {
+                println!("{:?}: 0x{:04x}", name, value);
+            }
+            debugger.view_memory_at(address as usize, 8);
+            Ok(())
+        }
+        other => other,
     }
 }
 
@@ -49,7 +49,7 @@ pub fn test_cpu() -> Result<(), CpuError>{
 
     let mut i = 0;
 
-    let mut cpu = Cpu::new(shared_memory.clone());
+    let cpu = Cpu::new(shared_memory.clone());
     { 
         // Cannot mutably borrow the same RefCell more than once in the same scope. This is disallowed by RefCell to ensure that the borrow rules are not violated at runtime.
         // That's why we have to create a new scope
@@ -74,7 +74,7 @@ pub fn test_cpu() -> Result<(), CpuError>{
         write_instruction!(writable_bytes, i, Instruction::Return.into());
     }
 
-    step_instruction_forever(&mut cpu)?;
+    run_with_debugger(cpu, sub_routine_address as u16)?;
 
     Ok(())
 
@@ -0,0 +1,84 @@
+use std::collections::HashSet;
+
+use crate::{
+    cpu::{Cpu, CpuError, RegisterName},
+    instructions::Instruction,
+};
+
+// Wraps a `Cpu` with a set of breakpoint addresses and the controls a front-end
+// needs to drive execution and inspect state only when it stops. Replaces the
+// old `step_instruction_forever` loop that printed and blocked on every single
+// instruction.
+pub struct Debugger {
+    cpu: Cpu,
+    breakpoints: HashSet<u16>,
+}
+
+impl Debugger {
+    pub fn new(cpu: Cpu) -> Self {
+        Debugger {
+            cpu,
+            breakpoints: HashSet::new(),
+        }
+    }
+
+    pub fn add_breakpoint(&mut self, address: u16) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u16) {
+        self.breakpoints.remove(&address);
+    }
+
+    // Step until Ip lands on a breakpoint (reported as `BreakpointHit`) or an
+    // instruction faults (reported as its own error).
+    pub fn run_until_break(&mut self) -> Result<(), CpuError> {
+        loop {
+            self.cpu.step()?;
+            let ip = *self.cpu.get_register(&RegisterName::Ip)?;
+            if self.breakpoints.contains(&ip) {
+                return Err(CpuError::BreakpointHit(ip));
+            }
+        }
+    }
+
+    // Execute one instruction, but treat a call as atomic: set a temporary
+    // breakpoint at the return address and run the subroutine to completion so
+    // the debugger steps over it rather than descending into it. A breakpoint
+    // reached inside the subroutine still stops it early with `BreakpointHit`.
+    pub fn step_over(&mut self) -> Result<(), CpuError> {
+        let ip = *self.cpu.get_register(&RegisterName::Ip)?;
+        let next = Instruction::try_from(self.cpu.peek_byte(ip)?)?;
+
+        let call_length = match next {
+            Instruction::CallLiteral => 3,  // opcode + 16-bit address
+            Instruction::CallRegister => 2, // opcode + register index
+            _ => {
+                return self.cpu.step();
+            }
+        };
+
+        // The return address is the instruction immediately after the call.
+        let return_address = ip.wrapping_add(call_length);
+        loop {
+            self.cpu.step()?;
+            let current = *self.cpu.get_register(&RegisterName::Ip)?;
+            if current == return_address {
+                return Ok(());
+            }
+            if self.breakpoints.contains(&current) {
+                return Err(CpuError::BreakpointHit(current));
+            }
+        }
+    }
+
+    // Read-only snapshot of all registers for a front-end to display on a stop.
+    pub fn register_snapshot(&self) -> Vec<(RegisterName, u16)> {
+        self.cpu.register_snapshot()
+    }
+
+    // A window of memory for a front-end to display on a stop.
+    pub fn view_memory_at(&self, address: usize, n: usize) {
+        self.cpu.view_memory_at(address, n);
+    }
+}
@@ -47,5 +47,31 @@ define_instruction! {
     MoveRegisterToMemory => 0x12,
     MoveMemoryToRegister => 0x13,
     AddRegisterToRegister => 0x14,
+    // Legacy jump: re-reads a literal and an address and branches when the
+    // literal differs from Acc. The flag-based conditional family below
+    // (JumpEq/JumpNe/... added with Cmp) is the general mechanism; JumpNotEq is
+    // kept for the programs that predate the flags register.
     JumpNotEq => 0x15,
+    PushLiteral => 0x16,
+    PushRegister => 0x17,
+    Pop => 0x18,
+    CallLiteral => 0x19,
+    CallRegister => 0x1a,
+    Return => 0x1b,
+    MathAdd => 0x1c,
+    MathSub => 0x1d,
+    MathMul => 0x1e,
+    MathDiv => 0x1f,
+    MathMod => 0x20,
+    Ecall => 0x21,
+    Cmp => 0x22,
+    // Flag-based conditional jumps tested against the status flags set by Cmp /
+    // the math family. JumpNe is the flag-based not-equal (Zero clear); it is
+    // distinct from the legacy Acc-comparing JumpNotEq (0x15) above.
+    JumpEq => 0x23,
+    JumpNe => 0x24,
+    JumpLt => 0x25,
+    JumpGt => 0x26,
+    JumpLtU => 0x27,
+    JumpGtU => 0x28,
 }